@@ -37,7 +37,6 @@ mod util;
 use util::*;
 
 mod app_entry;
-use app_entry::*;
 
 mod locale;
 use locale::*;
@@ -45,10 +44,17 @@ use locale::*;
 mod history;
 use history::*;
 
+mod provider;
+use provider::*;
+
 fn app_startup(application: &gtk::Application, daemon_mode: bool) {
     let config = Config::load();
-    let launch_cgroups = config.cgroups;
-    let cmd_prefix = config.command_prefix.clone();
+
+    // In daemon mode keep capturing clipboard selections for the history
+    // provider even while the launcher window is hidden.
+    if daemon_mode {
+        watch_clipboard(&config);
+    }
 
     let window = gtk::Window::builder()
         .application(application)
@@ -96,11 +102,27 @@ fn app_startup(application: &gtk::Application, daemon_mode: bool) {
     scroll.add(&listbox);
 
     let history = Rc::new(RefCell::new(load_history(config.prune_history)));
-    let entries = Rc::new(RefCell::new(load_entries(&config, &history.borrow())));
 
-    for row in (&entries.borrow() as &HashMap<ListBoxRow, AppEntry>).keys() {
-        listbox.add(row);
+    // The launcher is a union of ordered providers; the application provider is
+    // always first so prefixless queries keep matching `.desktop` entries.
+    let mut providers: Vec<Box<dyn SearchProvider>> = vec![
+        Box::new(AppProvider::new(&config, history.clone())),
+        Box::new(CommandProvider::new(&config)),
+        Box::new(CalcProvider::new(&config)),
+        Box::new(SessionProvider::new(&config)),
+        Box::new(ClipboardProvider::new(&config)),
+        Box::new(PlacesProvider::new(&config)),
+    ];
+    // Only available under a wlroots compositor advertising the protocol.
+    if let Some(windows) = WindowProvider::new(&config) {
+        providers.push(Box::new(windows));
     }
+    let providers = Rc::new(RefCell::new(providers));
+
+    // Maps each currently displayed row back to the provider that owns it, and
+    // to its score, so activation and sorting can be dispatched generically.
+    let owners: Rc<RefCell<HashMap<ListBoxRow, usize>>> = Rc::new(RefCell::new(HashMap::new()));
+    let scores: Rc<RefCell<HashMap<ListBoxRow, i64>>> = Rc::new(RefCell::new(HashMap::new()));
 
     fn hide_or_close(daemon_mode: bool, window: &gtk::Window, entry: &gtk::Entry) {
         if daemon_mode {
@@ -118,7 +140,7 @@ fn app_startup(application: &gtk::Application, daemon_mode: bool) {
     }
 
     window.connect_key_press_event(
-        clone!(entry, listbox, entries, daemon_mode => move |window, event| {
+        clone!(entry, listbox, daemon_mode => move |window, event| {
             use constants::*;
             #[allow(non_upper_case_globals)]
             Inhibit(match event.keyval() {
@@ -128,19 +150,12 @@ fn app_startup(application: &gtk::Application, daemon_mode: bool) {
                 },
                 Down | KP_Down | Tab if entry.has_focus() => {
                     if let Some(r0) = listbox.row_at_index(0) {
-                        let es = entries.borrow();
                         if r0.is_selected() {
                             if let Some(r1) = listbox.row_at_index(1) {
-                                if let Some(app_entry) = es.get(&r1) {
-                                    if !app_entry.hidden() {
-                                        listbox.select_row(Some(&r1));
-                                    }
-                                }
-                            }
-                        } else if let Some(app_entry) = es.get(&r0) {
-                            if !app_entry.hidden() {
-                                listbox.select_row(Some(&r0));
+                                listbox.select_row(Some(&r1));
                             }
+                        } else {
+                            listbox.select_row(Some(&r0));
                         }
                     }
                     false
@@ -166,63 +181,64 @@ fn app_startup(application: &gtk::Application, daemon_mode: bool) {
     }
 
     let matcher = SkimMatcherV2::default();
-    let term_command = config.term_command.clone();
-    entry.connect_changed(clone!(entries, listbox, cmd_prefix => move |e| {
-        let text = e.text();
-        let is_cmd = is_cmd(&text, &cmd_prefix);
-        {
-            let mut entries = entries.borrow_mut();
-            for entry in entries.values_mut() {
-                if is_cmd {
-                    entry.hide(); // hide entries in command mode
-                } else {
-                    entry.update_match(&text, &matcher, &config);
+    entry.connect_changed(
+        clone!(providers, owners, scores, listbox => move |e| {
+            let text = e.text();
+
+            // Drop the rows from the previous query and rebuild from the union
+            // of whichever providers match the current prefix.
+            for child in listbox.children() {
+                listbox.remove(&child);
+            }
+            let mut owners = owners.borrow_mut();
+            let mut scores = scores.borrow_mut();
+            owners.clear();
+            scores.clear();
+
+            let mut providers = providers.borrow_mut();
+            let (selected, query) = route(&providers, &text);
+            for i in selected {
+                for ProviderRow { row, score } in providers[i].query(query, &matcher, &config) {
+                    owners.insert(row.clone(), i);
+                    scores.insert(row.clone(), score);
+                    listbox.add(&row);
                 }
             }
-        }
-        listbox.invalidate_filter();
-        listbox.invalidate_sort();
-        listbox.select_row(listbox.row_at_index(0).as_ref());
-    }));
 
-    entry.connect_activate(clone!(listbox, window, daemon_mode => move |e| {
-        let text = e.text();
-        if is_cmd(&text, &cmd_prefix) { // command execution direct
-            let cmd_line = &text[cmd_prefix.len()..].trim();
-            launch_cmd(cmd_line);
-            hide_or_close(daemon_mode, &window, &e);
-        } else if let Some(row) = listbox.row_at_index(0) {
+            listbox.invalidate_sort();
+            listbox.select_row(listbox.row_at_index(0).as_ref());
+        }),
+    );
+
+    entry.connect_activate(clone!(listbox => move |_| {
+        if let Some(row) = listbox.row_at_index(0) {
             row.activate();
         }
     }));
 
     listbox.connect_row_activated(
-        clone!(entry, entries, window, history, daemon_mode => move |_, r| {
-            {
-                let es = entries.borrow();
-                let e = &es[r];
-                if !e.hidden() {
-                    launch_app(&e.info, term_command.as_deref(), launch_cgroups);
-
-                    let mut history = history.borrow_mut();
-                    update_history(&mut history, e.info.id().unwrap().as_str());
-                    save_history(&history);
-                }
+        clone!(entry, providers, owners, window, daemon_mode => move |_, r| {
+            let activation = {
+                let owners = owners.borrow();
+                let mut providers = providers.borrow_mut();
+                owners.get(r).map(|&i| providers[i].activate(r))
+            };
+            if let Some(Activation::Keep) = activation {
+                return;
             }
             hide_or_close(daemon_mode, &window, &entry);
         }),
     );
 
-    listbox.set_filter_func(Some(Box::new(clone!(entries => move |r| {
-        let e = entries.borrow();
-        !e[r].hidden()
-    }))));
-
-    listbox.set_sort_func(Some(Box::new(clone!(entries => move |a, b| {
-        let e = entries.borrow();
-        e[a].cmp(&e[b]) as i32
+    listbox.set_sort_func(Some(Box::new(clone!(scores => move |a, b| {
+        let scores = scores.borrow();
+        let sa = scores.get(a).copied().unwrap_or(0);
+        let sb = scores.get(b).copied().unwrap_or(0);
+        sb.cmp(&sa) as i32
     }))));
 
+    // Populate the listbox with the prefixless providers' initial rows.
+    entry.emit_by_name::<()>("changed", &[]);
     listbox.select_row(listbox.row_at_index(0).as_ref());
 
     window.add(&vbox);
@@ -0,0 +1,88 @@
+/*
+Copyright (C) 2020 Dorian Rudolph
+
+sirula is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+sirula is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with sirula.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use super::{Activation, ProviderRow, SearchProvider};
+use crate::app_entry::*;
+use crate::config::*;
+use crate::history::*;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use gtk::prelude::*;
+use gtk::ListBoxRow;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// The built-in application provider: the `.desktop` entries discovered by
+/// [`load_entries`], fuzzy-matched and ordered by launch history.
+pub struct AppProvider {
+    entries: HashMap<ListBoxRow, AppEntry>,
+    history: Rc<RefCell<History>>,
+    term_command: Option<String>,
+    cgroups: bool,
+}
+
+impl AppProvider {
+    pub fn new(config: &Config, history: Rc<RefCell<History>>) -> Self {
+        let entries = load_entries(config, &history.borrow());
+        AppProvider {
+            entries,
+            history,
+            term_command: config.term_command.clone(),
+            cgroups: config.cgroups,
+        }
+    }
+}
+
+impl SearchProvider for AppProvider {
+    fn query(&mut self, query: &str, matcher: &SkimMatcherV2, config: &Config) -> Vec<ProviderRow> {
+        for entry in self.entries.values_mut() {
+            entry.update_match(query, matcher, config);
+        }
+
+        // Order the surviving entries with the existing `AppEntry` comparison
+        // (history, then score, then name). Cold launches score at or below
+        // zero so any provider returning positive fuzzy scores (e.g. open
+        // windows) can be interleaved ahead of them while apps keep their
+        // relative order amongst themselves.
+        let mut matched: Vec<(&ListBoxRow, &AppEntry)> = self
+            .entries
+            .iter()
+            .filter(|(_, e)| !e.hidden())
+            .collect();
+        matched.sort_by(|(_, a), (_, b)| a.cmp(b));
+
+        matched
+            .into_iter()
+            .enumerate()
+            .map(|(rank, (row, _))| ProviderRow {
+                row: row.clone(),
+                score: -(rank as i64),
+            })
+            .collect()
+    }
+
+    fn activate(&mut self, row: &ListBoxRow) -> Activation {
+        let entry = &self.entries[row];
+        launch_app(&entry.info, self.term_command.as_deref(), self.cgroups);
+
+        let mut history = self.history.borrow_mut();
+        update_history(&mut history, entry.info.id().unwrap().as_str());
+        save_history(&history);
+
+        Activation::Close
+    }
+}
@@ -0,0 +1,337 @@
+/*
+Copyright (C) 2020 Dorian Rudolph
+
+sirula is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+sirula is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with sirula.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Inline calculator: evaluate an arithmetic expression and offer the result.
+
+use super::{build_row, Activation, ProviderRow, SearchProvider};
+use crate::config::*;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use gtk::prelude::*;
+use gtk::ListBoxRow;
+use std::collections::HashMap;
+
+/// Evaluates an arithmetic expression; activating the result copies it to the
+/// clipboard. On a parse error no row is produced (no error popup).
+pub struct CalcProvider {
+    prefix: Option<String>,
+    rows: HashMap<ListBoxRow, String>,
+}
+
+impl CalcProvider {
+    pub fn new(config: &Config) -> Self {
+        CalcProvider {
+            prefix: config.calc_prefix.clone(),
+            rows: HashMap::new(),
+        }
+    }
+}
+
+impl SearchProvider for CalcProvider {
+    fn prefix(&self) -> Option<&str> {
+        self.prefix.as_deref()
+    }
+
+    fn query(&mut self, query: &str, _matcher: &SkimMatcherV2, _config: &Config) -> Vec<ProviderRow> {
+        self.rows.clear();
+        // Without an explicit prefix the calculator runs against every query,
+        // so ignore bare numbers/constants (`e`, `pi`, `2`) that would
+        // otherwise hijack prefixless app search; require something that
+        // actually looks like an expression.
+        if self.prefix.is_none() && !looks_like_expression(query) {
+            return Vec::new();
+        }
+        let value = match evaluate(query) {
+            Some(v) => v,
+            None => return Vec::new(),
+        };
+        let text = format_result(value);
+        let row = build_row(None, &format!("= <b>{}</b>", glib::markup_escape_text(&text)));
+        self.rows.insert(row.clone(), text);
+        // Rank the result ahead of fuzzy matches with a bounded score rather
+        // than saturating the sort key.
+        vec![ProviderRow { row, score: CALC_SCORE }]
+    }
+
+    fn activate(&mut self, row: &ListBoxRow) -> Activation {
+        if let Some(text) = self.rows.get(row) {
+            let clipboard = gtk::Clipboard::get(&gdk::SELECTION_CLIPBOARD);
+            clipboard.set_text(text);
+            clipboard.store();
+        }
+        Activation::Close
+    }
+}
+
+/// Score for a calculator result: high enough to sit above app rows (scored at
+/// or below zero) and other providers' fuzzy matches without saturating the
+/// sort key.
+const CALC_SCORE: i64 = 1_000_000;
+
+/// Whether `query` contains an operator, parenthesis or function call — i.e.
+/// it is worth treating as an expression in prefixless mode.
+fn looks_like_expression(query: &str) -> bool {
+    query.contains(|c| "+-*/%^()".contains(c))
+}
+
+/// Trim trailing zeros so `2 + 2` reads `4`, not `4.0000000`.
+fn format_result(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        format!("{}", value as i64)
+    } else {
+        let s = format!("{:.10}", value);
+        s.trim_end_matches('0').trim_end_matches('.').to_owned()
+    }
+}
+
+/// Evaluate `expr`, returning `None` on any tokenizing or parsing failure.
+pub fn evaluate(expr: &str) -> Option<f64> {
+    let tokens = tokenize(expr)?;
+    if tokens.is_empty() {
+        return None;
+    }
+    let rpn = shunting_yard(tokens)?;
+    // Reject non-finite outcomes (e.g. `1/0` -> inf, `0/0` -> NaN) so they
+    // surface as no row rather than an `inf`/`NaN` result.
+    eval_rpn(&rpn).filter(|v| v.is_finite())
+}
+
+#[derive(Clone, PartialEq)]
+enum Token {
+    Num(f64),
+    Op(char),
+    Func(String),
+    LParen,
+    RParen,
+}
+
+fn tokenize(expr: &str) -> Option<Vec<Token>> {
+    let chars: Vec<char> = expr.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || c == '.' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let num: f64 = chars[start..i].iter().collect::<String>().parse().ok()?;
+            tokens.push(Token::Num(num));
+        } else if c.is_ascii_alphabetic() {
+            let start = i;
+            while i < chars.len() && chars[i].is_ascii_alphabetic() {
+                i += 1;
+            }
+            let name: String = chars[start..i].iter().collect();
+            match constant(&name) {
+                Some(value) => tokens.push(Token::Num(value)),
+                None => tokens.push(Token::Func(name)),
+            }
+        } else if "+-*/%^".contains(c) {
+            // Distinguish unary minus from subtraction by what precedes it.
+            let unary = matches!(
+                tokens.last(),
+                None | Some(Token::Op(_)) | Some(Token::LParen)
+            );
+            if c == '-' && unary {
+                tokens.push(Token::Op('~'));
+            } else {
+                tokens.push(Token::Op(c));
+            }
+            i += 1;
+        } else if c == '(' {
+            tokens.push(Token::LParen);
+            i += 1;
+        } else if c == ')' {
+            tokens.push(Token::RParen);
+            i += 1;
+        } else {
+            return None;
+        }
+    }
+    Some(tokens)
+}
+
+fn constant(name: &str) -> Option<f64> {
+    match name {
+        "pi" => Some(std::f64::consts::PI),
+        "e" => Some(std::f64::consts::E),
+        _ => None,
+    }
+}
+
+/// Binding power of a binary operator; `~` is the unary-minus marker.
+///
+/// Unary minus shares `^`'s level (both right-associative) so that `-2^2`
+/// parses as `-(2^2) = -4`, matching the usual calculator convention, while
+/// still binding tighter than `*`/`+` (`-2*3 = -6`).
+fn precedence(op: char) -> u8 {
+    match op {
+        '~' | '^' => 3,
+        '*' | '/' | '%' => 2,
+        '+' | '-' => 1,
+        _ => 0,
+    }
+}
+
+fn right_associative(op: char) -> bool {
+    op == '^' || op == '~'
+}
+
+fn shunting_yard(tokens: Vec<Token>) -> Option<Vec<Token>> {
+    let mut output = Vec::new();
+    let mut stack: Vec<Token> = Vec::new();
+    for token in tokens {
+        match token {
+            Token::Num(_) => output.push(token),
+            Token::Func(_) => stack.push(token),
+            Token::Op(op) => {
+                while let Some(top) = stack.last() {
+                    match top {
+                        Token::Op(top_op) => {
+                            let higher = precedence(*top_op) > precedence(op)
+                                || (precedence(*top_op) == precedence(op)
+                                    && !right_associative(op));
+                            if higher {
+                                output.push(stack.pop().unwrap());
+                            } else {
+                                break;
+                            }
+                        }
+                        Token::Func(_) => output.push(stack.pop().unwrap()),
+                        _ => break,
+                    }
+                }
+                stack.push(Token::Op(op));
+            }
+            Token::LParen => stack.push(token),
+            Token::RParen => {
+                loop {
+                    match stack.pop() {
+                        Some(Token::LParen) => break,
+                        Some(t) => output.push(t),
+                        None => return None,
+                    }
+                }
+                if let Some(Token::Func(_)) = stack.last() {
+                    output.push(stack.pop().unwrap());
+                }
+            }
+        }
+    }
+    while let Some(t) = stack.pop() {
+        if matches!(t, Token::LParen | Token::RParen) {
+            return None;
+        }
+        output.push(t);
+    }
+    Some(output)
+}
+
+fn eval_rpn(rpn: &[Token]) -> Option<f64> {
+    let mut stack: Vec<f64> = Vec::new();
+    for token in rpn {
+        match token {
+            Token::Num(n) => stack.push(*n),
+            Token::Op('~') => {
+                let a = stack.pop()?;
+                stack.push(-a);
+            }
+            Token::Op(op) => {
+                let b = stack.pop()?;
+                let a = stack.pop()?;
+                stack.push(match op {
+                    '+' => a + b,
+                    '-' => a - b,
+                    '*' => a * b,
+                    '/' => a / b,
+                    '%' => a % b,
+                    '^' => a.powf(b),
+                    _ => return None,
+                });
+            }
+            Token::Func(name) => {
+                let a = stack.pop()?;
+                stack.push(match name.as_str() {
+                    "sqrt" => a.sqrt(),
+                    "sin" => a.sin(),
+                    "cos" => a.cos(),
+                    "log" => a.ln(),
+                    _ => return None,
+                });
+            }
+            _ => return None,
+        }
+    }
+    if stack.len() == 1 {
+        stack.pop()
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{evaluate, format_result};
+
+    fn eval(expr: &str) -> f64 {
+        evaluate(expr).unwrap()
+    }
+
+    #[test]
+    fn precedence_and_associativity() {
+        assert_eq!(eval("2*3+4"), 10.0);
+        assert_eq!(eval("2+3*4"), 14.0);
+        // `^` is right-associative: 2^(3^2) = 2^9 = 512.
+        assert_eq!(eval("2^3^2"), 512.0);
+        assert_eq!(eval("(2+3)*4"), 20.0);
+    }
+
+    #[test]
+    fn unary_vs_binary_minus() {
+        assert_eq!(eval("2--3"), 5.0);
+        assert_eq!(eval("-2*3"), -6.0);
+        // Unary minus shares `^`'s right-associative level, so `-2^2` is `-(2^2) = -4`.
+        assert_eq!(eval("-2^2"), -4.0);
+    }
+
+    #[test]
+    fn functions_and_constants() {
+        assert_eq!(eval("sqrt(4)"), 2.0);
+        assert_eq!(eval("cos(0)"), 1.0);
+        assert!((eval("pi") - std::f64::consts::PI).abs() < 1e-12);
+        assert!((eval("e") - std::f64::consts::E).abs() < 1e-12);
+    }
+
+    #[test]
+    fn non_finite_and_parse_failures_yield_none() {
+        assert_eq!(evaluate("1/0"), None);
+        assert_eq!(evaluate("0/0"), None);
+        assert_eq!(evaluate("2+"), None);
+        assert_eq!(evaluate("("), None);
+        assert_eq!(evaluate(""), None);
+        assert_eq!(evaluate("foo"), None);
+    }
+
+    #[test]
+    fn results_format_without_trailing_zeros() {
+        assert_eq!(format_result(4.0), "4");
+        assert_eq!(format_result(0.5), "0.5");
+    }
+}
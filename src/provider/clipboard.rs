@@ -0,0 +1,146 @@
+/*
+Copyright (C) 2020 Dorian Rudolph
+
+sirula is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+sirula is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with sirula.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Clipboard history: a persistent ring buffer of copied text, fed by the
+//! daemon and browsed from the launcher.
+
+use super::{build_row, Activation, ProviderRow, SearchProvider};
+use crate::config::*;
+use crate::util::*;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use gtk::prelude::*;
+use gtk::ListBoxRow;
+use std::collections::HashMap;
+use std::fs;
+
+/// The stored clipboard buffer, newest first.
+pub type ClipboardHistory = Vec<String>;
+
+/// Path to the persisted buffer, in the same XDG state dir as launch history.
+fn clipboard_path() -> std::path::PathBuf {
+    get_xdg_state_file("clipboard.json")
+}
+
+/// Load the clipboard buffer, returning an empty one when it does not exist.
+pub fn load_clipboard() -> ClipboardHistory {
+    fs::read_to_string(clipboard_path())
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+/// Persist the clipboard buffer, ignoring write errors like `save_history`.
+pub fn save_clipboard(history: &ClipboardHistory) {
+    if let Ok(s) = serde_json::to_string(history) {
+        let path = clipboard_path();
+        if let Some(dir) = path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        let _ = fs::write(path, s);
+    }
+}
+
+/// Push `text` to the front, de-duplicating and capping to `size` entries.
+pub fn update_clipboard(history: &mut ClipboardHistory, text: String, size: usize) {
+    if text.is_empty() {
+        return;
+    }
+    history.retain(|e| e != &text);
+    history.insert(0, text);
+    prune_clipboard(history, size);
+}
+
+/// Drop the oldest entries beyond the configured cap.
+pub fn prune_clipboard(history: &mut ClipboardHistory, size: usize) {
+    if history.len() > size {
+        history.truncate(size);
+    }
+}
+
+/// Browses the clipboard buffer, newest first, behind a configurable prefix.
+pub struct ClipboardProvider {
+    prefix: Option<String>,
+    rows: HashMap<ListBoxRow, String>,
+}
+
+impl ClipboardProvider {
+    pub fn new(config: &Config) -> Self {
+        ClipboardProvider {
+            prefix: config.clipboard_prefix.clone(),
+            rows: HashMap::new(),
+        }
+    }
+}
+
+impl SearchProvider for ClipboardProvider {
+    fn prefix(&self) -> Option<&str> {
+        self.prefix.as_deref()
+    }
+
+    fn query(&mut self, query: &str, matcher: &SkimMatcherV2, _config: &Config) -> Vec<ProviderRow> {
+        self.rows.clear();
+        // Reload each time so entries captured by the daemon show up.
+        let history = load_clipboard();
+        let mut result = Vec::new();
+        for (rank, text) in history.iter().enumerate() {
+            let score = if query.is_empty() {
+                // Preserve newest-first order when nothing is typed.
+                (history.len() - rank) as i64
+            } else {
+                match matcher.fuzzy_match(text, query) {
+                    Some(s) => s,
+                    None => continue,
+                }
+            };
+            let preview: String = text.lines().next().unwrap_or_default().chars().take(80).collect();
+            let row = build_row(None, &glib::markup_escape_text(&preview));
+            self.rows.insert(row.clone(), text.clone());
+            result.push(ProviderRow { row, score });
+        }
+        result
+    }
+
+    fn activate(&mut self, row: &ListBoxRow) -> Activation {
+        if let Some(text) = self.rows.get(row) {
+            let clipboard = gtk::Clipboard::get(&gdk::SELECTION_CLIPBOARD);
+            clipboard.set_text(text);
+            clipboard.store();
+        }
+        Activation::Close
+    }
+}
+
+/// Install the daemon-side clipboard watcher: every time the clipboard owner
+/// changes, append the new text to the persistent ring buffer. A no-op when
+/// capture is disabled in `Config`.
+pub fn watch_clipboard(config: &Config) {
+    if !config.clipboard_enable {
+        return;
+    }
+    let size = config.clipboard_size;
+    let clipboard = gtk::Clipboard::get(&gdk::SELECTION_CLIPBOARD);
+    clipboard.connect_owner_change(move |clipboard, _| {
+        clipboard.request_text(move |_, text| {
+            if let Some(text) = text {
+                let mut history = load_clipboard();
+                update_clipboard(&mut history, text.to_string(), size);
+                save_clipboard(&history);
+            }
+        });
+    });
+}
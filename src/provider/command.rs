@@ -0,0 +1,60 @@
+/*
+Copyright (C) 2020 Dorian Rudolph
+
+sirula is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+sirula is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with sirula.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use super::{build_row, Activation, ProviderRow, SearchProvider};
+use crate::config::*;
+use crate::util::*;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use gtk::ListBoxRow;
+use std::collections::HashMap;
+
+/// Runs the query verbatim as a shell command line, behind `command_prefix`.
+pub struct CommandProvider {
+    prefix: String,
+    rows: HashMap<ListBoxRow, String>,
+}
+
+impl CommandProvider {
+    pub fn new(config: &Config) -> Self {
+        CommandProvider {
+            prefix: config.command_prefix.clone(),
+            rows: HashMap::new(),
+        }
+    }
+}
+
+impl SearchProvider for CommandProvider {
+    fn prefix(&self) -> Option<&str> {
+        Some(&self.prefix)
+    }
+
+    fn query(&mut self, query: &str, _matcher: &SkimMatcherV2, _config: &Config) -> Vec<ProviderRow> {
+        self.rows.clear();
+        let cmd_line = query.trim();
+        if cmd_line.is_empty() {
+            return Vec::new();
+        }
+        let row = build_row(None, &glib::markup_escape_text(cmd_line));
+        self.rows.insert(row.clone(), cmd_line.to_owned());
+        vec![ProviderRow { row, score: 0 }]
+    }
+
+    fn activate(&mut self, row: &ListBoxRow) -> Activation {
+        launch_cmd(&self.rows[row]);
+        Activation::Close
+    }
+}
@@ -0,0 +1,142 @@
+/*
+Copyright (C) 2020 Dorian Rudolph
+
+sirula is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+sirula is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with sirula.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Pluggable search providers.
+//!
+//! Everything the launcher can surface — applications, commands, open
+//! windows, calculator results, … — is a [`SearchProvider`]. The entry text
+//! is routed to whichever provider owns its prefix (or to every prefixless
+//! provider when the text carries no prefix), and the union of their rows is
+//! rebuilt into the listbox on every keystroke.
+
+use crate::config::*;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use gtk::prelude::*;
+use gtk::{builders::BoxBuilder, builders::LabelBuilder, Image, ListBoxRow};
+
+mod app;
+pub use app::*;
+
+mod command;
+pub use command::*;
+
+mod window;
+pub use window::*;
+
+mod calc;
+pub use calc::*;
+
+mod session;
+pub use session::*;
+
+mod clipboard;
+pub use clipboard::*;
+
+mod places;
+pub use places::*;
+
+/// A single candidate produced by a provider for the current query.
+///
+/// The widget is inserted into the shared listbox verbatim; `score` orders it
+/// against rows from every other provider (higher sorts first).
+pub struct ProviderRow {
+    pub row: ListBoxRow,
+    pub score: i64,
+}
+
+/// What should happen to the launcher once a row has been activated.
+pub enum Activation {
+    /// The action ran; hide or close the launcher as usual.
+    Close,
+    /// Keep the window open (e.g. awaiting a confirmation keypress).
+    Keep,
+}
+
+/// A source of list rows behind an optional activation prefix.
+pub trait SearchProvider {
+    /// The prefix that scopes the query to this provider, if any. A prefixless
+    /// provider always runs when the text carries no recognised prefix.
+    fn prefix(&self) -> Option<&str> {
+        None
+    }
+
+    /// Produce the rows matching `query` (with any prefix already stripped).
+    fn query(&mut self, query: &str, matcher: &SkimMatcherV2, config: &Config) -> Vec<ProviderRow>;
+
+    /// Activate a row previously returned by [`query`](Self::query).
+    fn activate(&mut self, row: &ListBoxRow) -> Activation;
+}
+
+/// Build a listbox row carrying an optional themed icon and a markup label,
+/// laid out like the application rows produced by [`AppEntry`].
+pub fn build_row(icon: Option<&Image>, markup: &str) -> ListBoxRow {
+    let hbox = BoxBuilder::new()
+        .orientation(gtk::Orientation::Horizontal)
+        .build();
+    if let Some(image) = icon {
+        hbox.pack_start(image, false, false, 0);
+    }
+    let label = LabelBuilder::new()
+        .xalign(0.0)
+        .use_markup(true)
+        .label(markup)
+        .build();
+    hbox.pack_start(&label, true, true, 0);
+
+    let row = ListBoxRow::new();
+    row.add(&hbox);
+    row.show_all();
+    row
+}
+
+/// Replace the markup label of a row previously built with [`build_row`].
+pub fn relabel_row(row: &ListBoxRow, markup: &str) {
+    if let Some(hbox) = row.child().and_then(|c| c.downcast::<gtk::Box>().ok()) {
+        for child in hbox.children() {
+            if let Ok(label) = child.downcast::<gtk::Label>() {
+                label.set_markup(markup);
+            }
+        }
+    }
+}
+
+/// Select the providers a query should be dispatched to and strip the prefix.
+///
+/// If the text begins with a provider's prefix, only that provider runs and
+/// the returned slice of the text has the prefix removed. Otherwise every
+/// prefixless provider runs against the full text.
+pub fn route<'a>(
+    providers: &[Box<dyn SearchProvider>],
+    text: &'a str,
+) -> (Vec<usize>, &'a str) {
+    for (i, p) in providers.iter().enumerate() {
+        // An empty prefix is treated as "prefixless": otherwise a mis-set
+        // `command_prefix` would match every query and swallow app search.
+        if let Some(prefix) = p.prefix().filter(|p| !p.is_empty()) {
+            if let Some(rest) = text.strip_prefix(prefix) {
+                return (vec![i], rest);
+            }
+        }
+    }
+    let prefixless = providers
+        .iter()
+        .enumerate()
+        .filter(|(_, p)| p.prefix().map_or(true, |p| p.is_empty()))
+        .map(|(i, _)| i)
+        .collect();
+    (prefixless, text)
+}
@@ -0,0 +1,180 @@
+/*
+Copyright (C) 2020 Dorian Rudolph
+
+sirula is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+sirula is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with sirula.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Places and devices: mounted volumes, removable drives, GTK bookmarks and
+//! the XDG user directories.
+
+use super::{build_row, Activation, ProviderRow, SearchProvider};
+use crate::config::*;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use gio::prelude::*;
+use gtk::prelude::*;
+use gtk::{Image, ListBoxRow};
+use std::collections::HashMap;
+use std::fs;
+
+/// A single browsable location.
+#[derive(Clone)]
+enum Place {
+    /// An openable URI (a directory or an already-mounted volume).
+    Uri(String),
+    /// A volume that must be mounted before its root can be opened.
+    Volume(gio::Volume),
+}
+
+/// Lists filesystem places behind a configurable prefix (default `/`), opening
+/// the chosen one in the user's file manager.
+pub struct PlacesProvider {
+    prefix: Option<String>,
+    rows: HashMap<ListBoxRow, (String, Place)>,
+    icon_size: i32,
+}
+
+impl PlacesProvider {
+    pub fn new(config: &Config) -> Self {
+        PlacesProvider {
+            prefix: config.places_prefix.clone(),
+            rows: HashMap::new(),
+            icon_size: config.icon_size,
+        }
+    }
+
+    /// Gather user dirs, GTK bookmarks and the volume monitor's devices.
+    fn places(&self) -> Vec<(String, Option<gio::Icon>, Place)> {
+        let mut places = Vec::new();
+
+        // XDG user directories, home first.
+        if let Some(home) = glib::home_dir() {
+            places.push(dir_place("Home", &home));
+        }
+        use glib::UserDirectory::*;
+        for dir in [Desktop, Documents, Downloads, Music, Pictures, Videos, PublicShare, Templates] {
+            if let Some(path) = glib::user_special_dir(dir) {
+                let label = path.file_name().and_then(|n| n.to_str()).unwrap_or("").to_owned();
+                places.push(dir_place(&label, &path));
+            }
+        }
+
+        // GTK bookmarks: `file:///path [optional label]` per line.
+        let bookmarks = glib::user_config_dir().join("gtk-3.0").join("bookmarks");
+        if let Ok(contents) = fs::read_to_string(bookmarks) {
+            for line in contents.lines() {
+                let mut parts = line.splitn(2, ' ');
+                if let Some(uri) = parts.next().filter(|u| !u.is_empty()) {
+                    let label = parts
+                        .next()
+                        .map(|l| l.to_owned())
+                        .or_else(|| uri.rsplit('/').next().map(|s| s.to_owned()))
+                        .unwrap_or_else(|| uri.to_owned());
+                    places.push((label, folder_icon(), Place::Uri(uri.to_owned())));
+                }
+            }
+        }
+
+        // Mounted volumes and removable drives via the volume monitor.
+        let monitor = gio::VolumeMonitor::get();
+        for mount in monitor.mounts() {
+            let label = mount.name().to_string();
+            let uri = mount.root().uri().to_string();
+            places.push((label, Some(mount.icon()), Place::Uri(uri)));
+        }
+        for volume in monitor.volumes() {
+            if volume.get_mount().is_none() {
+                let label = volume.name().to_string();
+                places.push((label, Some(volume.icon()), Place::Volume(volume)));
+            }
+        }
+
+        places
+    }
+
+    fn image(&self, icon: Option<&gio::Icon>) -> Option<Image> {
+        icon.map(|icon| {
+            let image = Image::from_gicon(icon, gtk::IconSize::LargeToolbar);
+            image.set_pixel_size(self.icon_size);
+            image
+        })
+    }
+}
+
+fn dir_place(label: &str, path: &std::path::Path) -> (String, Option<gio::Icon>, Place) {
+    let uri = glib::filename_to_uri(path, None)
+        .map(|s| s.to_string())
+        .unwrap_or_else(|_| format!("file://{}", path.display()));
+    (label.to_owned(), folder_icon(), Place::Uri(uri))
+}
+
+fn folder_icon() -> Option<gio::Icon> {
+    Some(gio::ThemedIcon::new("folder").upcast())
+}
+
+impl SearchProvider for PlacesProvider {
+    fn prefix(&self) -> Option<&str> {
+        self.prefix.as_deref()
+    }
+
+    fn query(&mut self, query: &str, matcher: &SkimMatcherV2, _config: &Config) -> Vec<ProviderRow> {
+        self.rows.clear();
+        let mut result = Vec::new();
+        for (label, icon, place) in self.places() {
+            let score = if query.is_empty() {
+                0
+            } else {
+                match matcher.fuzzy_match(&label, query) {
+                    Some(s) => s,
+                    None => continue,
+                }
+            };
+            let row = build_row(self.image(icon.as_ref()).as_ref(), &glib::markup_escape_text(&label));
+            self.rows.insert(row.clone(), (label, place));
+            result.push(ProviderRow { row, score });
+        }
+        result
+    }
+
+    fn activate(&mut self, row: &ListBoxRow) -> Activation {
+        match self.rows.get(row) {
+            Some((_, Place::Uri(uri))) => open_uri(uri),
+            Some((_, Place::Volume(volume))) => {
+                // Mount first, then open the resulting root once the async
+                // operation completes.
+                let op = gio::MountOperation::new();
+                let volume = volume.clone();
+                volume.clone().mount(
+                    gio::MountMountFlags::NONE,
+                    Some(&op),
+                    gio::Cancellable::NONE,
+                    move |result| {
+                        if result.is_ok() {
+                            if let Some(mount) = volume.get_mount() {
+                                open_uri(&mount.root().uri());
+                            }
+                        }
+                    },
+                );
+            }
+            None => {}
+        }
+        Activation::Close
+    }
+}
+
+/// Open a URI in the user's default handler.
+fn open_uri(uri: &str) {
+    let _ = gio::AppInfo::launch_default_for_uri(uri, gio::AppLaunchContext::NONE);
+}
@@ -0,0 +1,147 @@
+/*
+Copyright (C) 2020 Dorian Rudolph
+
+sirula is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+sirula is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with sirula.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Session/power actions: lock, log out, suspend, hibernate, reboot, shutdown.
+
+use super::{build_row, relabel_row, Activation, ProviderRow, SearchProvider};
+use crate::config::*;
+use crate::util::*;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use gtk::prelude::*;
+use gtk::{IconLookupFlags, IconTheme, Image, ListBoxRow};
+use std::collections::HashMap;
+
+/// A single session action: a label, a themed icon, the shell command that
+/// carries it out, and whether it needs a confirmation keypress.
+struct Action {
+    key: &'static str,
+    label: &'static str,
+    icon: &'static str,
+    default_command: &'static str,
+    destructive: bool,
+}
+
+/// The built-in actions, in the order gnome-pie's sessionGroup presents them.
+/// Commands are overridable per action through `Config::session_commands`.
+const ACTIONS: &[Action] = &[
+    Action { key: "lock",      label: "Lock",      icon: "system-lock-screen", default_command: "loginctl lock-session", destructive: false },
+    Action { key: "logout",    label: "Log Out",   icon: "system-log-out",     default_command: "loginctl terminate-session self", destructive: true },
+    Action { key: "suspend",   label: "Suspend",   icon: "system-suspend",     default_command: "systemctl suspend", destructive: false },
+    Action { key: "hibernate", label: "Hibernate", icon: "system-hibernate",   default_command: "systemctl hibernate", destructive: false },
+    Action { key: "reboot",    label: "Reboot",    icon: "system-reboot",      default_command: "systemctl reboot", destructive: true },
+    Action { key: "shutdown",  label: "Shut Down", icon: "system-shutdown",    default_command: "systemctl poweroff", destructive: true },
+];
+
+/// Surfaces the session actions as launchable rows behind a configurable
+/// prefix (default `!`).
+pub struct SessionProvider {
+    prefix: Option<String>,
+    commands: HashMap<&'static str, String>,
+    confirm: bool,
+    icon_theme: IconTheme,
+    icon_size: i32,
+    rows: HashMap<ListBoxRow, &'static str>,
+    /// The action currently awaiting its confirmation keypress, if any.
+    armed: Option<&'static str>,
+}
+
+impl SessionProvider {
+    pub fn new(config: &Config) -> Self {
+        let commands = ACTIONS
+            .iter()
+            .map(|a| {
+                let cmd = config
+                    .session_commands
+                    .get(a.key)
+                    .cloned()
+                    .unwrap_or_else(|| a.default_command.to_owned());
+                (a.key, cmd)
+            })
+            .collect();
+        SessionProvider {
+            prefix: config.session_prefix.clone(),
+            commands,
+            confirm: config.session_confirm,
+            icon_theme: IconTheme::default().unwrap_or_else(IconTheme::new),
+            icon_size: config.icon_size,
+            rows: HashMap::new(),
+            armed: None,
+        }
+    }
+
+    fn icon_for(&self, name: &str) -> Option<Image> {
+        let pixbuf = self
+            .icon_theme
+            .load_icon(name, self.icon_size, IconLookupFlags::USE_BUILTIN)
+            .ok()
+            .flatten()?;
+        Some(Image::from_pixbuf(Some(&pixbuf)))
+    }
+}
+
+impl SearchProvider for SessionProvider {
+    fn prefix(&self) -> Option<&str> {
+        self.prefix.as_deref()
+    }
+
+    fn query(&mut self, query: &str, matcher: &SkimMatcherV2, _config: &Config) -> Vec<ProviderRow> {
+        self.rows.clear();
+        self.armed = None;
+        let mut result = Vec::new();
+        for action in ACTIONS {
+            let score = if query.is_empty() {
+                0
+            } else {
+                match matcher.fuzzy_match(action.label, query) {
+                    Some(s) => s,
+                    None => continue,
+                }
+            };
+            let row = build_row(
+                self.icon_for(action.icon).as_ref(),
+                &glib::markup_escape_text(action.label),
+            );
+            self.rows.insert(row.clone(), action.key);
+            result.push(ProviderRow { row, score });
+        }
+        result
+    }
+
+    fn activate(&mut self, row: &ListBoxRow) -> Activation {
+        let key = match self.rows.get(row) {
+            Some(key) => *key,
+            None => return Activation::Close,
+        };
+        let action = ACTIONS.iter().find(|a| a.key == key).unwrap();
+
+        // Destructive actions require a second activation to go through. Re-label
+        // the row so the pending confirmation is visible rather than looking
+        // like a dead keypress.
+        if self.confirm && action.destructive && self.armed != Some(key) {
+            self.armed = Some(key);
+            relabel_row(
+                row,
+                &glib::markup_escape_text(&format!("Confirm {}?", action.label)),
+            );
+            return Activation::Keep;
+        }
+
+        launch_cmd(&self.commands[key]);
+        Activation::Close
+    }
+}
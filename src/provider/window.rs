@@ -0,0 +1,191 @@
+/*
+Copyright (C) 2020 Dorian Rudolph
+
+sirula is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+sirula is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with sirula.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Open-window switcher backed by `zwlr_foreign_toplevel_management_v1`.
+
+use super::{build_row, Activation, ProviderRow, SearchProvider};
+use crate::config::*;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use gdk::prelude::*;
+use gtk::prelude::*;
+use gtk::{IconLookupFlags, IconTheme, Image, ListBoxRow};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::os::unix::io::AsRawFd;
+use std::rc::Rc;
+
+use wayland_client::protocol::wl_seat::WlSeat;
+use wayland_client::{Display, GlobalManager, Main};
+use wayland_protocols::wlr::unstable::foreign_toplevel::v1::client::{
+    zwlr_foreign_toplevel_handle_v1::{Event as ToplevelEvent, ZwlrForeignToplevelHandleV1},
+    zwlr_foreign_toplevel_manager_v1::{Event as ManagerEvent, ZwlrForeignToplevelManagerV1},
+};
+
+/// A single open toplevel surface as reported by the compositor.
+#[derive(Default, Clone)]
+struct Toplevel {
+    handle: Option<ZwlrForeignToplevelHandleV1>,
+    title: String,
+    app_id: String,
+}
+
+/// Window-switcher provider. Fuzzy-matches against each toplevel's title and
+/// `app_id`; activating a row raises the window instead of launching anything.
+pub struct WindowProvider {
+    prefix: Option<String>,
+    display: Display,
+    seat: Main<WlSeat>,
+    #[allow(dead_code)]
+    manager: Main<ZwlrForeignToplevelManagerV1>,
+    toplevels: Rc<RefCell<HashMap<u32, Toplevel>>>,
+    rows: HashMap<ListBoxRow, ZwlrForeignToplevelHandleV1>,
+    icon_theme: IconTheme,
+    icon_size: i32,
+}
+
+impl WindowProvider {
+    /// Bind the foreign-toplevel manager on the default Wayland display and
+    /// start tracking open windows. Returns `None` when the compositor does
+    /// not advertise the protocol (e.g. running nested under X11).
+    pub fn new(config: &Config) -> Option<Self> {
+        let display = Display::connect_to_env().ok()?;
+        let mut queue = display.create_event_queue();
+        let attached = (*display).clone().attach(queue.token());
+        let globals = GlobalManager::new(&attached);
+        queue
+            .sync_roundtrip(&mut (), |_, _, _| unreachable!())
+            .ok()?;
+
+        let seat = globals.instantiate_exact::<WlSeat>(1).ok()?;
+        // The `activate` request exists since v1, so bind whatever the
+        // compositor advertises down to v1 rather than hard-requiring v3.
+        let manager = globals
+            .instantiate_range::<ZwlrForeignToplevelManagerV1>(1, 3)
+            .ok()?;
+
+        let toplevels: Rc<RefCell<HashMap<u32, Toplevel>>> = Rc::new(RefCell::new(HashMap::new()));
+        manager.quick_assign(clone!(toplevels => move |_, event, _| {
+            if let ManagerEvent::Toplevel { toplevel } = event {
+                let id = toplevel.as_ref().id();
+                toplevels.borrow_mut().insert(id, Toplevel {
+                    handle: Some(toplevel.detach()),
+                    ..Default::default()
+                });
+                toplevel.quick_assign(clone!(toplevels => move |h, event, _| {
+                    let id = h.as_ref().id();
+                    let mut tops = toplevels.borrow_mut();
+                    match event {
+                        ToplevelEvent::Title { title } => {
+                            if let Some(t) = tops.get_mut(&id) { t.title = title; }
+                        }
+                        ToplevelEvent::AppId { app_id } => {
+                            if let Some(t) = tops.get_mut(&id) { t.app_id = app_id; }
+                        }
+                        ToplevelEvent::Closed => { tops.remove(&id); }
+                        _ => {}
+                    }
+                }));
+            }
+        }));
+
+        // Let the initial burst of state events settle before the first query.
+        queue.sync_roundtrip(&mut (), |_, _, _| {}).ok()?;
+
+        // Keep the queue pumping for the rest of the process: watch the
+        // connection fd from the GLib main loop and dispatch whenever the
+        // compositor has events, so the `toplevels` map tracks live windows
+        // (essential in `--daemon` mode, where the provider outlives startup).
+        let fd = display.as_raw_fd();
+        let queue = Rc::new(RefCell::new(queue));
+        glib::source::unix_fd_add_local(
+            fd,
+            glib::IOCondition::IN,
+            clone!(display, queue => move |_, _| {
+                let _ = display.flush();
+                let _ = queue.borrow_mut().dispatch_pending(&mut (), |_, _, _| {});
+                glib::Continue(true)
+            }),
+        );
+
+        Some(WindowProvider {
+            prefix: config.window_prefix.clone(),
+            display,
+            seat,
+            manager,
+            toplevels,
+            rows: HashMap::new(),
+            icon_theme: IconTheme::default().unwrap_or_else(IconTheme::new),
+            icon_size: config.icon_size,
+        })
+    }
+
+    /// Resolve a toplevel's icon the same way `load_entries` does for
+    /// `AppInfo`: look the `app_id` up in the active icon theme.
+    fn icon_for(&self, app_id: &str) -> Option<Image> {
+        let pixbuf = self
+            .icon_theme
+            .load_icon(app_id, self.icon_size, IconLookupFlags::USE_BUILTIN)
+            .ok()
+            .flatten()?;
+        Some(Image::from_pixbuf(Some(&pixbuf)))
+    }
+}
+
+impl SearchProvider for WindowProvider {
+    fn prefix(&self) -> Option<&str> {
+        self.prefix.as_deref()
+    }
+
+    fn query(&mut self, query: &str, matcher: &SkimMatcherV2, _config: &Config) -> Vec<ProviderRow> {
+        self.rows.clear();
+        let tops = self.toplevels.borrow();
+        let mut result = Vec::new();
+        for top in tops.values() {
+            let haystack = format!("{} {}", top.title, top.app_id);
+            let score = if query.is_empty() {
+                0
+            } else {
+                match matcher.fuzzy_match(&haystack, query) {
+                    Some(s) => s,
+                    None => continue,
+                }
+            };
+            let handle = match &top.handle {
+                Some(h) => h.clone(),
+                None => continue,
+            };
+            let row = build_row(
+                self.icon_for(&top.app_id).as_ref(),
+                &glib::markup_escape_text(&top.title),
+            );
+            self.rows.insert(row.clone(), handle);
+            result.push(ProviderRow { row, score });
+        }
+        result
+    }
+
+    fn activate(&mut self, row: &ListBoxRow) -> Activation {
+        if let Some(handle) = self.rows.get(row) {
+            handle.activate(&self.seat);
+            // The request is buffered on our own connection, which GTK does
+            // not flush — push it to the compositor ourselves.
+            let _ = self.display.flush();
+        }
+        Activation::Close
+    }
+}